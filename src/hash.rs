@@ -0,0 +1,38 @@
+use sha2::{Digest, Sha256};
+
+/// Hex-encoded SHA-256 digest of `bytes`, used as the content-addressed
+/// store key for stored Markdown.
+pub fn content_hash(bytes: &[u8]) -> String {
+    let digest = Sha256::digest(bytes);
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Store key for `hash`'s stored Markdown content.
+pub fn content_key(hash: &str) -> String {
+    format!("markdown/{}.md", hash)
+}
+
+/// Store key for the sidecar object recording when `hash`'s content was first stored.
+pub fn meta_key(hash: &str) -> String {
+    format!("markdown/{}.meta", hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn content_hash_matches_known_sha256() {
+        assert_eq!(
+            content_hash(b"hello world"),
+            "b94d27b9934d3e08a52e52d7da7dabfac484efe37a5380ee9088f7ace2efcde9"
+        );
+    }
+
+    #[test]
+    fn content_and_meta_keys_share_the_hash_but_not_the_extension() {
+        let hash = content_hash(b"some markdown");
+        assert_eq!(content_key(&hash), format!("markdown/{}.md", hash));
+        assert_eq!(meta_key(&hash), format!("markdown/{}.meta", hash));
+    }
+}
@@ -1,7 +1,16 @@
 use worker::*;
 use serde::{Deserialize, Serialize};
-use reqwest::Client;
-use std::time::Duration;
+
+mod hash;
+mod manifest;
+mod metrics;
+mod queue;
+mod scrape;
+mod serve;
+mod store;
+
+use queue::ScrapeJob;
+use scrape::{build_client, fetch_links, RetryConfig};
 
 // Request payload for the API
 #[derive(Deserialize)]
@@ -9,12 +18,48 @@ struct ScrapeRequest {
     url: String,
 }
 
-// Response structure for the API
+// Response structure for the API.
+//
+// This only carries what's knowable synchronously: the job id and the
+// source URLs enqueued for fetching. The queue consumer hasn't run yet, so
+// there's no content hash/URL to return here — once a link is fetched and
+// stored, resolve its current content URL via `GET /manifest?url=<source>`.
 #[derive(Serialize)]
 struct ScrapeResponse {
     success: bool,
-    files: Vec<String>,
+    job_id: String,
+    pending: Vec<String>,
     error: Option<String>,
+    stats: Option<ScrapeStats>,
+}
+
+// Self-describing stats for a single API call, so large crawls stay
+// debuggable without cross-referencing `/metrics`.
+//
+// `links_discovered` and `total_duration_ms` describe this call: the fetch
+// handler only enqueues jobs, so that's as far as "this call" goes. The
+// actual Markdown fetch + dedup happens later in the queue consumer,
+// possibly in a different isolate, so there's no per-call dedup count to
+// report yet. `dedup_skipped_isolate_total` is named and documented as what
+// it actually is: the cumulative counter for whichever isolate happened to
+// handle this request, not an outcome of this scrape.
+#[derive(Serialize)]
+struct ScrapeStats {
+    links_discovered: usize,
+    total_duration_ms: u64,
+    dedup_skipped_isolate_total: u64,
+}
+
+impl ScrapeResponse {
+    fn error(message: String) -> ScrapeResponse {
+        ScrapeResponse {
+            success: false,
+            job_id: String::new(),
+            pending: vec![],
+            error: Some(message),
+            stats: None,
+        }
+    }
 }
 
 // Structure for Cloudflare Browser Rendering API responses
@@ -29,6 +74,23 @@ struct BrowserRenderingResponse<T> {
 pub async fn main(req: Request, env: Env, _ctx: worker::Context) -> Result<Response> {
     console_error_panic_hook::set_once();
 
+    // Serve stored Markdown directly, rather than relying on a guessed
+    // public store domain.
+    if req.method() == Method::Get {
+        let path = req.path();
+        if let Some(key) = path.strip_prefix("/markdown/") {
+            return serve::get_markdown(&req, &env, key).await;
+        }
+        if path == "/metrics" {
+            return Response::ok(metrics::render_prometheus())
+                .map(|r| r.with_headers(prometheus_headers()));
+        }
+        if path == "/manifest" {
+            return serve::get_manifest(&req, &env).await;
+        }
+        return Response::error("Not Found", 404);
+    }
+
     // Only accept POST requests
     if req.method() != Method::Post {
         return Response::error("Method Not Allowed", 405);
@@ -37,202 +99,108 @@ pub async fn main(req: Request, env: Env, _ctx: worker::Context) -> Result<Respo
     // Parse the request body
     let scrape_request: ScrapeRequest = match req.json().await {
         Ok(data) => data,
-        Err(e) => return Response::from_json(&ScrapeResponse {
-            success: false,
-            files: vec![],
-            error: Some(format!("Invalid request body: {}", e)),
-        }).map(|r| r.with_status(400)),
+        Err(e) => return Response::from_json(&ScrapeResponse::error(format!("Invalid request body: {}", e)))
+            .map(|r| r.with_status(400)),
     };
 
     // Get API token and account ID from environment
     let api_token = match env.secret("CLOUDFLARE_API_TOKEN") {
         Ok(token) => token.to_string(),
-        Err(e) => return Response::from_json(&ScrapeResponse {
-            success: false,
-            files: vec![],
-            error: Some(format!("Missing API token: {}", e)),
-        }).map(|r| r.with_status(500)),
+        Err(e) => return Response::from_json(&ScrapeResponse::error(format!("Missing API token: {}", e)))
+            .map(|r| r.with_status(500)),
     };
     let account_id = match env.secret("CLOUDFLARE_ACCOUNT_ID") {
         Ok(id) => id.to_string(),
-        Err(e) => return Response::from_json(&ScrapeResponse {
-            success: false,
-            files: vec![],
-            error: Some(format!("Missing account ID: {}", e)),
-        }).map(|r| r.with_status(500)),
+        Err(e) => return Response::from_json(&ScrapeResponse::error(format!("Missing account ID: {}", e)))
+            .map(|r| r.with_status(500)),
     };
 
-    // Initialize R2 bucket
-    let bucket = match env.bucket("SCRAPER_BUCKET") {
-        Ok(bucket) => bucket,
-        Err(e) => return Response::from_json(&ScrapeResponse {
-            success: false,
-            files: vec![],
-            error: Some(format!("Failed to access R2 bucket: {}", e)),
-        }).map(|r| r.with_status(500)),
+    // Initialize the scrape queue
+    let scrape_queue = match env.queue("SCRAPE_QUEUE") {
+        Ok(queue) => queue,
+        Err(e) => return Response::from_json(&ScrapeResponse::error(format!("Failed to access scrape queue: {}", e)))
+            .map(|r| r.with_status(500)),
     };
 
-    // Perform the scrape
-    match scrape_and_store(&scrape_request.url, &api_token, &account_id, bucket).await {
-        Ok(files) => Response::from_json(&ScrapeResponse {
-            success: true,
-            files,
-            error: None,
-        }),
-        Err(e) => Response::from_json(&ScrapeResponse {
-            success: false,
-            files: vec![],
-            error: Some(format!("Scraping failed: {}", e)),
-        }).map(|r| r.with_status(500)),
-    }
-}
-
-// Main scraping and storage logic
-async fn scrape_and_store(url: &str, api_token: &str, account_id: &str, bucket: Bucket) -> Result<Vec<String>> {
-    let client = Client::builder()
-        .timeout(Duration::from_secs(10))
-        .user_agent("Cloudflare-Worker-Scraper/1.0")
-        .build()
-        .map_err(|e| worker::Error::RustError(format!("Failed to create client: {}", e)))?;
-
-    // Fetch links
-    let links = fetch_links(&client, url, api_token, account_id)
-        .await
-        .map_err(|e| worker::Error::RustError(format!("Failed to fetch links: {}", e)))?;
-
-    let mut file_urls = vec![];
-
-    // Fetch Markdown and store for each link
-    for link in links {
-        match fetch_markdown(&client, &link, api_token, account_id).await {
-            Ok(markdown) => {
-                // Generate a unique file name (e.g., based on URL and timestamp)
-                let file_name = format!(
-                    "markdown/{}.md",
-                    url_to_filename(&link)
-                );
-
-                // Store in R2
-                let file_content = markdown.as_bytes();
-                bucket
-                    .put(&file_name, file_content)
-                    .execute()
-                    .await
-                    .map_err(|e| worker::Error::RustError(format!("Failed to store {}: {}", file_name, e)))?;
-
-                // Construct public URL (assuming R2 bucket is publicly accessible)
-                let public_url = format!("https://<your-r2-bucket-public-domain>/{file_name}");
-                file_urls.push(public_url);
-            }
-            Err(e) => {
-                console_log!("Failed to fetch Markdown for {}: {}", link, e);
-                continue; // Continue with other links
-            }
+    // Fetch the link list and enqueue one job per link; the actual Markdown
+    // fetch + store happens in the queue consumer so the fetch handler can
+    // return well within the Worker's wall-clock budget.
+    metrics::record_scrape_attempted();
+    let started = Date::now().as_millis();
+    let retry_config = RetryConfig::from_env(&env);
+    match enqueue_scrape(&scrape_request.url, &api_token, &account_id, &scrape_queue, &retry_config).await {
+        Ok((job_id, pending)) => {
+            metrics::record_links_discovered(pending.len() as u64);
+            Response::from_json(&ScrapeResponse {
+                success: true,
+                job_id,
+                stats: Some(ScrapeStats {
+                    links_discovered: pending.len(),
+                    total_duration_ms: Date::now().as_millis().saturating_sub(started),
+                    dedup_skipped_isolate_total: metrics::snapshot().dedup_skipped,
+                }),
+                pending,
+                error: None,
+            })
         }
+        Err(e) => Response::from_json(&ScrapeResponse::error(format!("Scraping failed: {}", e)))
+            .map(|r| r.with_status(500)),
     }
-
-    Ok(file_urls)
 }
 
-// Fetch links using Browser Rendering API
-async fn fetch_links(client: &Client, url: &str, api_token: &str, account_id: &str) -> Result<Vec<String>> {
-    let api_url = format!(
-        "https://api.cloudflare.com/client/v4/accounts/{}/browser-rendering/links",
-        account_id
-    );
-
-    let response = retry_request(|| {
-        client
-            .post(&api_url)
-            .header("Authorization", format!("Bearer {}", api_token))
-            .header("Content-Type", "application/json")
-            .json(&serde_json::json!({ "url": url }))
-            .send()
-    })
-    .await
-    .map_err(|e| worker::Error::RustError(format!("Links request failed: {}", e)))?;
-
-    let json: BrowserRenderingResponse<Vec<String>> = response
-        .json()
-        .await
-        .map_err(|e| worker::Error::RustError(format!("Failed to parse links response: {}", e)))?;
-
-    if !json.success {
-        return Err(worker::Error::RustError("Links API returned success: false".to_string()));
-    }
+fn prometheus_headers() -> Headers {
+    let mut headers = Headers::new();
+    let _ = headers.set("Content-Type", "text/plain; version=0.0.4");
+    headers
+}
 
-    Ok(json.result)
+// Queue consumer: fans out to one Markdown fetch + R2 store per message.
+#[event(queue)]
+pub async fn consumer(batch: MessageBatch<ScrapeJob>, env: Env, _ctx: worker::Context) -> Result<()> {
+    console_error_panic_hook::set_once();
+    queue::consume(batch, env).await
 }
 
-// Fetch Markdown using Browser Rendering API
-async fn fetch_markdown(client: &Client, url: &str, api_token: &str, account_id: &str) -> Result<String> {
-    let api_url = format!(
-        "https://api.cloudflare.com/client/v4/accounts/{}/browser-rendering/markdown",
-        account_id
-    );
-
-    let response = retry_request(|| {
-        client
-            .post(&api_url)
-            .header("Authorization", format!("Bearer {}", api_token))
-            .header("Content-Type", "application/json")
-            .json(&serde_json::json!({ "url": url }))
-            .send()
-    })
-    .await
-    .map_err(|e| worker::Error::RustError(format!("Markdown request failed: {}", e)))?;
-
-    let json: BrowserRenderingResponse<String> = response
-        .json()
+// Fetch the link list for `url` and enqueue one job per link.
+async fn enqueue_scrape(
+    url: &str,
+    api_token: &str,
+    account_id: &str,
+    scrape_queue: &Queue,
+    retry_config: &RetryConfig,
+) -> Result<(String, Vec<String>)> {
+    let client = build_client()?;
+
+    let links = fetch_links(&client, url, api_token, account_id, retry_config)
         .await
-        .map_err(|e| worker::Error::RustError(format!("Failed to parse markdown response: {}", e)))?;
-
-    if !json.success {
-        return Err(worker::Error::RustError("Markdown API returned success: false".to_string()));
-    }
-
-    Ok(json.result)
-}
+        .map_err(|e| worker::Error::RustError(format!("Failed to fetch links: {}", e)))?;
 
-// Retry logic for HTTP requests
-async fn retry_request<F, Fut>(mut request: F) -> Result<reqwest::Response>
-where
-    F: FnMut() -> Fut,
-    Fut: std::future::Future<Output = reqwest::Result<reqwest::Response>>,
-{
-    let max_retries = 3;
-    let retry_delay = Duration::from_secs(2);
-    let mut attempt = 0;
-
-    loop {
-        match request().await {
-            Ok(response) if response.status().is_success() => return Ok(response),
-            Ok(response) => {
-                if attempt >= max_retries {
-                    return Err(worker::Error::RustError(format!(
-                        "HTTP error after {} attempts: {}",
-                        max_retries,
-                        response.status()
-                    )));
-                }
-            }
-            Err(e) => {
-                if attempt >= max_retries {
-                    return Err(worker::Error::RustError(format!(
-                        "Request failed after {} attempts: {}",
-                        max_retries, e
-                    )));
-                }
-            }
+    let job_id = new_job_id();
+
+    for (enqueued, link) in links.iter().enumerate() {
+        let job = ScrapeJob {
+            job_id: job_id.clone(),
+            url: link.clone(),
+        };
+        if let Err(e) = scrape_queue.send(&job).await {
+            // `enqueued` jobs are already on the queue and will be processed
+            // independently; a client retry re-enqueues (and re-fetches)
+            // them, so log which links already went through.
+            console_log!(
+                "job {}: enqueue failed for {} after {} of {} links already enqueued: {}",
+                job_id, link, enqueued, links.len(), e
+            );
+            return Err(worker::Error::RustError(format!(
+                "Failed to enqueue {} ({} of {} links already enqueued): {}",
+                link, enqueued, links.len(), e
+            )));
         }
-        attempt += 1;
-        console_log!("Retry attempt {} for request", attempt);
-        worker::Delay::from(retry_delay).await;
     }
+
+    Ok((job_id, links))
 }
 
-// Helper to convert URL to a safe filename
-fn url_to_filename(url: &str) -> String {
-    let safe_url = url.replace("://", "_").replace("/", "_").replace(".", "_");
-    format!("{}_{}", safe_url, chrono::Utc::now().timestamp_millis())
+// Generate a job id unique enough to correlate queue messages and logs.
+fn new_job_id() -> String {
+    format!("{}-{:x}", chrono::Utc::now().timestamp_millis(), (js_sys::Math::random() * u32::MAX as f64) as u32)
 }
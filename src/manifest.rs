@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+use worker::Result;
+
+use crate::hash::content_hash;
+use crate::store::Store;
+
+/// Latest content hash/timestamp recorded for a source URL.
+#[derive(Deserialize, Serialize, Clone)]
+pub struct ManifestEntry {
+    pub url: String,
+    pub hash: String,
+    pub fetched_at: i64,
+}
+
+/// Store key a URL's manifest entry lives under (keyed per URL, not one shared blob).
+fn manifest_key(url: &str) -> String {
+    format!("manifest/{}.json", content_hash(url.as_bytes()))
+}
+
+/// Record the latest content hash/timestamp for `entry.url`, overwriting any
+/// previous entry for that URL.
+pub async fn record(store: &dyn Store, entry: &ManifestEntry) -> Result<()> {
+    let bytes = serde_json::to_vec(entry)
+        .map_err(|e| worker::Error::RustError(format!("Failed to serialize manifest entry: {}", e)))?;
+    store.put(&manifest_key(&entry.url), bytes).await
+}
+
+/// Resolve the latest stored content hash/timestamp for `url`, so callers
+/// can go from a source URL to the current version of its Markdown.
+pub async fn lookup(store: &dyn Store, url: &str) -> Result<Option<ManifestEntry>> {
+    match store.get(&manifest_key(url)).await? {
+        Some(bytes) => Ok(serde_json::from_slice(&bytes).ok()),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest_key_is_stable_and_url_specific() {
+        assert_eq!(manifest_key("https://example.com/a"), manifest_key("https://example.com/a"));
+        assert_ne!(manifest_key("https://example.com/a"), manifest_key("https://example.com/b"));
+    }
+}
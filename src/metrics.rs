@@ -0,0 +1,173 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use serde::Serialize;
+
+// Process-global counters, in the spirit of pict-rs's `init_metrics` /
+// kittybox's `metrics` module. Note these only accumulate for the lifetime
+// of the isolate handling requests — Workers isolates are recycled, so
+// absolute values reset over time; treat `/metrics` as a sampling window
+// rather than a durable total.
+static SCRAPES_ATTEMPTED: AtomicU64 = AtomicU64::new(0);
+static LINKS_DISCOVERED: AtomicU64 = AtomicU64::new(0);
+static MARKDOWN_FETCH_SUCCEEDED: AtomicU64 = AtomicU64::new(0);
+static MARKDOWN_FETCH_FAILED: AtomicU64 = AtomicU64::new(0);
+static RETRIES: AtomicU64 = AtomicU64::new(0);
+static BYTES_STORED: AtomicU64 = AtomicU64::new(0);
+static DEDUP_SKIPPED: AtomicU64 = AtomicU64::new(0);
+static MARKDOWN_FETCH_DURATION_MS_TOTAL: AtomicU64 = AtomicU64::new(0);
+
+/// Upper bounds (milliseconds) for the per-link fetch duration histogram, in
+/// the Prometheus convention: each bucket counts observations <= its bound,
+/// on top of an implicit `+Inf` bucket equal to the total observation count.
+const FETCH_DURATION_BUCKETS_MS: [u64; 7] = [100, 250, 500, 1_000, 2_500, 5_000, 10_000];
+
+static FETCH_DURATION_BUCKET_COUNTS: [AtomicU64; FETCH_DURATION_BUCKETS_MS.len()] = [
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+    AtomicU64::new(0),
+];
+
+pub fn record_scrape_attempted() {
+    SCRAPES_ATTEMPTED.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn record_links_discovered(count: u64) {
+    LINKS_DISCOVERED.fetch_add(count, Ordering::Relaxed);
+}
+
+pub fn record_markdown_fetch_succeeded(duration_ms: u64) {
+    MARKDOWN_FETCH_SUCCEEDED.fetch_add(1, Ordering::Relaxed);
+    MARKDOWN_FETCH_DURATION_MS_TOTAL.fetch_add(duration_ms, Ordering::Relaxed);
+    for (bound, bucket) in FETCH_DURATION_BUCKETS_MS.iter().zip(FETCH_DURATION_BUCKET_COUNTS.iter()) {
+        if duration_ms <= *bound {
+            bucket.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+}
+
+pub fn record_markdown_fetch_failed() {
+    MARKDOWN_FETCH_FAILED.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn record_retry() {
+    RETRIES.fetch_add(1, Ordering::Relaxed);
+}
+
+pub fn record_bytes_stored(bytes: u64) {
+    BYTES_STORED.fetch_add(bytes, Ordering::Relaxed);
+}
+
+pub fn record_dedup_skipped() {
+    DEDUP_SKIPPED.fetch_add(1, Ordering::Relaxed);
+}
+
+/// Point-in-time read of every counter, embedded in `ScrapeResponse.stats`
+/// and rendered by [`render_prometheus`]. `markdown_fetch_duration_buckets`
+/// pairs each bound in [`FETCH_DURATION_BUCKETS_MS`] with its cumulative
+/// observation count, so `/metrics` can show p50/p95 fetch latency.
+#[derive(Serialize)]
+pub struct Snapshot {
+    pub scrapes_attempted: u64,
+    pub links_discovered: u64,
+    pub markdown_fetch_succeeded: u64,
+    pub markdown_fetch_failed: u64,
+    pub retries: u64,
+    pub bytes_stored: u64,
+    pub dedup_skipped: u64,
+    pub markdown_fetch_duration_ms_total: u64,
+    pub markdown_fetch_duration_buckets: Vec<(u64, u64)>,
+}
+
+pub fn snapshot() -> Snapshot {
+    Snapshot {
+        scrapes_attempted: SCRAPES_ATTEMPTED.load(Ordering::Relaxed),
+        links_discovered: LINKS_DISCOVERED.load(Ordering::Relaxed),
+        markdown_fetch_succeeded: MARKDOWN_FETCH_SUCCEEDED.load(Ordering::Relaxed),
+        markdown_fetch_failed: MARKDOWN_FETCH_FAILED.load(Ordering::Relaxed),
+        retries: RETRIES.load(Ordering::Relaxed),
+        bytes_stored: BYTES_STORED.load(Ordering::Relaxed),
+        dedup_skipped: DEDUP_SKIPPED.load(Ordering::Relaxed),
+        markdown_fetch_duration_ms_total: MARKDOWN_FETCH_DURATION_MS_TOTAL.load(Ordering::Relaxed),
+        markdown_fetch_duration_buckets: FETCH_DURATION_BUCKETS_MS
+            .iter()
+            .zip(FETCH_DURATION_BUCKET_COUNTS.iter())
+            .map(|(bound, count)| (*bound, count.load(Ordering::Relaxed)))
+            .collect(),
+    }
+}
+
+/// Render the current snapshot in Prometheus text exposition format for
+/// `GET /metrics`.
+pub fn render_prometheus() -> String {
+    let snapshot = snapshot();
+    let mut out = String::new();
+
+    let counter = |out: &mut String, name: &str, help: &str, value: u64| {
+        out.push_str(&format!("# HELP {name} {help}\n# TYPE {name} counter\n{name} {value}\n"));
+    };
+
+    counter(&mut out, "scrape_attempts_total", "Total scrape requests received", snapshot.scrapes_attempted);
+    counter(&mut out, "scrape_links_discovered_total", "Total links discovered across all scrapes", snapshot.links_discovered);
+    counter(&mut out, "scrape_markdown_fetch_succeeded_total", "Markdown fetches that succeeded", snapshot.markdown_fetch_succeeded);
+    counter(&mut out, "scrape_markdown_fetch_failed_total", "Markdown fetches that failed", snapshot.markdown_fetch_failed);
+    counter(&mut out, "scrape_retries_total", "HTTP requests retried", snapshot.retries);
+    counter(&mut out, "scrape_bytes_stored_total", "Bytes written to the store", snapshot.bytes_stored);
+    counter(&mut out, "scrape_dedup_skipped_total", "Uploads skipped because the content hash already existed", snapshot.dedup_skipped);
+
+    let name = "scrape_markdown_fetch_duration_ms";
+    out.push_str(&format!("# HELP {name} Per-link Markdown fetch duration.\n# TYPE {name} histogram\n"));
+    for (bound, count) in &snapshot.markdown_fetch_duration_buckets {
+        out.push_str(&format!("{name}_bucket{{le=\"{bound}\"}} {count}\n"));
+    }
+    out.push_str(&format!("{name}_bucket{{le=\"+Inf\"}} {}\n", snapshot.markdown_fetch_succeeded));
+    out.push_str(&format!("{name}_sum {}\n", snapshot.markdown_fetch_duration_ms_total));
+    out.push_str(&format!("{name}_count {}\n", snapshot.markdown_fetch_succeeded));
+
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // These counters are process-global, and cargo runs tests in parallel on
+    // the same process, so assert on deltas rather than absolute values.
+
+    #[test]
+    fn record_scrape_attempted_increments_the_snapshot() {
+        let before = snapshot().scrapes_attempted;
+        record_scrape_attempted();
+        assert_eq!(snapshot().scrapes_attempted, before + 1);
+    }
+
+    #[test]
+    fn record_markdown_fetch_succeeded_updates_duration_total_and_buckets() {
+        let before = snapshot();
+        record_markdown_fetch_succeeded(50);
+        let after = snapshot();
+
+        assert_eq!(after.markdown_fetch_succeeded, before.markdown_fetch_succeeded + 1);
+        assert_eq!(after.markdown_fetch_duration_ms_total, before.markdown_fetch_duration_ms_total + 50);
+        // A 50ms observation falls within every bucket bound (the smallest is 100ms).
+        for ((_, before_count), (_, after_count)) in
+            before.markdown_fetch_duration_buckets.iter().zip(after.markdown_fetch_duration_buckets.iter())
+        {
+            assert_eq!(*after_count, before_count + 1);
+        }
+    }
+
+    #[test]
+    fn render_prometheus_contains_expected_metric_names() {
+        let out = render_prometheus();
+        assert!(out.contains("scrape_attempts_total"));
+        assert!(out.contains("scrape_dedup_skipped_total"));
+        assert!(out.contains("scrape_markdown_fetch_duration_ms_bucket{le=\"100\"}"));
+        assert!(out.contains("scrape_markdown_fetch_duration_ms_bucket{le=\"+Inf\"}"));
+        assert!(out.contains("scrape_markdown_fetch_duration_ms_sum"));
+        assert!(out.contains("scrape_markdown_fetch_duration_ms_count"));
+    }
+}
@@ -0,0 +1,168 @@
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use worker::{Date, Env, MessageBatch, Result};
+
+use crate::hash::{content_hash, content_key, meta_key};
+use crate::manifest::{self, ManifestEntry};
+use crate::scrape::{build_client, fetch_markdown, RetryConfig};
+use crate::store::{build_store, Store};
+
+/// Default number of Markdown fetches to run concurrently when draining a
+/// batch, overridable via the `MAX_CONCURRENT_FETCHES` env var.
+const DEFAULT_CONCURRENCY: usize = 6;
+
+/// A single per-link unit of work placed on the `SCRAPE_QUEUE` binding by the
+/// fetch handler and picked up by [`consume`].
+#[derive(Deserialize, Serialize)]
+pub struct ScrapeJob {
+    pub job_id: String,
+    pub url: String,
+}
+
+/// Queue consumer: fetches and stores Markdown for each message in the
+/// batch, bounded to [`concurrency_limit`] concurrent fetches, acking or
+/// retrying each message (and its manifest entry) independently.
+pub async fn consume(batch: MessageBatch<ScrapeJob>, env: Env) -> Result<()> {
+    let api_token = env.secret("CLOUDFLARE_API_TOKEN")?.to_string();
+    let account_id = env.secret("CLOUDFLARE_ACCOUNT_ID")?.to_string();
+    let store = build_store(&env)?;
+    let client = build_client()?;
+    let retry_config = RetryConfig::from_env(&env);
+    let limit = concurrency_limit(&env);
+
+    stream::iter(batch.messages()?)
+        .for_each_concurrent(limit, |message| {
+            let client = &client;
+            let api_token = &api_token;
+            let account_id = &account_id;
+            let retry_config = &retry_config;
+            let store = store.as_ref();
+            async move {
+                let job = message.body();
+                let fetch_started = Date::now().as_millis();
+                match fetch_markdown(client, &job.url, api_token, account_id, retry_config).await {
+                    Ok(markdown) => {
+                        crate::metrics::record_markdown_fetch_succeeded(
+                            Date::now().as_millis().saturating_sub(fetch_started),
+                        );
+                        let fetched_at = Date::now().as_millis() as i64;
+                        match store_content(store, &markdown, fetched_at).await {
+                            Ok(hash) => {
+                                let entry = ManifestEntry {
+                                    url: job.url.clone(),
+                                    hash,
+                                    fetched_at,
+                                };
+                                match manifest::record(store, &entry).await {
+                                    Ok(()) => message.ack(),
+                                    Err(e) => {
+                                        console_log!("job {}: failed to record manifest entry for {}: {}", job.job_id, job.url, e);
+                                        message.retry();
+                                    }
+                                }
+                            }
+                            Err(e) => {
+                                console_log!("job {}: failed to store {}: {}", job.job_id, job.url, e);
+                                message.retry();
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        crate::metrics::record_markdown_fetch_failed();
+                        console_log!("job {}: failed to fetch Markdown for {}: {}", job.job_id, job.url, e);
+                        message.retry();
+                    }
+                }
+            }
+        })
+        .await;
+
+    Ok(())
+}
+
+/// Store `markdown` under its content-addressed key, skipping the upload
+/// (dedup) when an object with that hash already exists, and returning the
+/// hash either way. On a miss, also writes a `<hash>.meta` sidecar recording
+/// `fetched_at` so `GET /markdown/<key>` can serve `Last-Modified`.
+async fn store_content(store: &dyn Store, markdown: &str, fetched_at: i64) -> Result<String> {
+    let hash = content_hash(markdown.as_bytes());
+    let key = content_key(&hash);
+
+    if store.head(&key).await? {
+        crate::metrics::record_dedup_skipped();
+        return Ok(hash);
+    }
+
+    let bytes = markdown.as_bytes().to_vec();
+    crate::metrics::record_bytes_stored(bytes.len() as u64);
+    store.put(&key, bytes).await?;
+
+    let meta = serde_json::to_vec(&fetched_at)
+        .map_err(|e| worker::Error::RustError(format!("Failed to serialize object metadata: {}", e)))?;
+    store.put(&meta_key(&hash), meta).await?;
+
+    Ok(hash)
+}
+
+/// Read the configurable in-flight fetch cap from the `MAX_CONCURRENT_FETCHES`
+/// env var, falling back to [`DEFAULT_CONCURRENCY`] when unset or invalid.
+fn concurrency_limit(env: &Env) -> usize {
+    env.var("MAX_CONCURRENT_FETCHES")
+        .ok()
+        .and_then(|v| v.to_string().parse::<usize>().ok())
+        .filter(|&n| n > 0)
+        .unwrap_or(DEFAULT_CONCURRENCY)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use std::cell::RefCell;
+    use std::collections::HashMap;
+
+    /// In-memory `Store` test double, standing in for R2/KV/S3.
+    #[derive(Default)]
+    struct FakeStore {
+        objects: RefCell<HashMap<String, Vec<u8>>>,
+    }
+
+    #[async_trait(?Send)]
+    impl Store for FakeStore {
+        async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<()> {
+            self.objects.borrow_mut().insert(key.to_string(), bytes);
+            Ok(())
+        }
+
+        async fn head(&self, key: &str) -> Result<bool> {
+            Ok(self.objects.borrow().contains_key(key))
+        }
+
+        async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+            Ok(self.objects.borrow().get(key).cloned())
+        }
+    }
+
+    #[test]
+    fn store_content_writes_content_and_meta_on_miss() {
+        let store = FakeStore::default();
+        let hash = futures::executor::block_on(store_content(&store, "hello", 1_000)).unwrap();
+
+        assert_eq!(store.objects.borrow().get(&content_key(&hash)), Some(&b"hello".to_vec()));
+        let meta: i64 = serde_json::from_slice(store.objects.borrow().get(&meta_key(&hash)).unwrap()).unwrap();
+        assert_eq!(meta, 1_000);
+    }
+
+    #[test]
+    fn store_content_skips_put_when_hash_already_stored() {
+        let store = FakeStore::default();
+        let hash = content_hash(b"hello");
+        store.objects.borrow_mut().insert(content_key(&hash), b"hello".to_vec());
+
+        let returned_hash = futures::executor::block_on(store_content(&store, "hello", 1_000)).unwrap();
+
+        assert_eq!(returned_hash, hash);
+        // Dedup skips the meta sidecar too — it was already written the first time this hash was stored.
+        assert!(store.objects.borrow().get(&meta_key(&hash)).is_none());
+    }
+}
@@ -0,0 +1,260 @@
+use chrono::Utc;
+use reqwest::{Client, StatusCode};
+use std::time::Duration;
+use worker::Env;
+
+use crate::BrowserRenderingResponse;
+
+/// Default retry policy, used when the corresponding env var isn't set.
+const DEFAULT_MAX_RETRIES: u32 = 3;
+const DEFAULT_BASE_DELAY_MS: u64 = 2_000;
+const DEFAULT_MAX_DELAY_MS: u64 = 30_000;
+
+/// Retry policy for upstream Browser Rendering API calls, threaded from env
+/// config instead of hardcoded constants.
+#[derive(Clone, Copy)]
+pub struct RetryConfig {
+    pub max_retries: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl RetryConfig {
+    pub fn from_env(env: &Env) -> Self {
+        RetryConfig {
+            max_retries: env_value(env, "RETRY_MAX_ATTEMPTS").unwrap_or(DEFAULT_MAX_RETRIES as u64) as u32,
+            base_delay: Duration::from_millis(env_value(env, "RETRY_BASE_DELAY_MS").unwrap_or(DEFAULT_BASE_DELAY_MS)),
+            max_delay: Duration::from_millis(env_value(env, "RETRY_MAX_DELAY_MS").unwrap_or(DEFAULT_MAX_DELAY_MS)),
+        }
+    }
+}
+
+fn env_value(env: &Env, key: &str) -> Option<u64> {
+    env.var(key).ok().and_then(|v| v.to_string().parse().ok())
+}
+
+/// Fetch the list of links on a page using the Browser Rendering API.
+pub async fn fetch_links(
+    client: &Client,
+    url: &str,
+    api_token: &str,
+    account_id: &str,
+    retry_config: &RetryConfig,
+) -> worker::Result<Vec<String>> {
+    let api_url = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/browser-rendering/links",
+        account_id
+    );
+
+    let response = retry_request(retry_config, || {
+        client
+            .post(&api_url)
+            .header("Authorization", format!("Bearer {}", api_token))
+            .header("Content-Type", "application/json")
+            .json(&serde_json::json!({ "url": url }))
+            .send()
+    })
+    .await
+    .map_err(|e| worker::Error::RustError(format!("Links request failed: {}", e)))?;
+
+    let json: BrowserRenderingResponse<Vec<String>> = response
+        .json()
+        .await
+        .map_err(|e| worker::Error::RustError(format!("Failed to parse links response: {}", e)))?;
+
+    if !json.success {
+        return Err(worker::Error::RustError("Links API returned success: false".to_string()));
+    }
+
+    Ok(json.result)
+}
+
+/// Fetch the Markdown rendering of a single page using the Browser Rendering API.
+pub async fn fetch_markdown(
+    client: &Client,
+    url: &str,
+    api_token: &str,
+    account_id: &str,
+    retry_config: &RetryConfig,
+) -> worker::Result<String> {
+    let api_url = format!(
+        "https://api.cloudflare.com/client/v4/accounts/{}/browser-rendering/markdown",
+        account_id
+    );
+
+    let response = retry_request(retry_config, || {
+        client
+            .post(&api_url)
+            .header("Authorization", format!("Bearer {}", api_token))
+            .header("Content-Type", "application/json")
+            .json(&serde_json::json!({ "url": url }))
+            .send()
+    })
+    .await
+    .map_err(|e| worker::Error::RustError(format!("Markdown request failed: {}", e)))?;
+
+    let json: BrowserRenderingResponse<String> = response
+        .json()
+        .await
+        .map_err(|e| worker::Error::RustError(format!("Failed to parse markdown response: {}", e)))?;
+
+    if !json.success {
+        return Err(worker::Error::RustError("Markdown API returned success: false".to_string()));
+    }
+
+    Ok(json.result)
+}
+
+/// Retry logic for HTTP requests: exponential backoff with full jitter,
+/// honoring `Retry-After` when the upstream sends one, and only retrying
+/// idempotent/transient statuses (429, 500, 502, 503, 504) — anything else
+/// (e.g. 400/401/403) fails fast.
+pub async fn retry_request<F, Fut>(retry_config: &RetryConfig, mut request: F) -> worker::Result<reqwest::Response>
+where
+    F: FnMut() -> Fut,
+    Fut: std::future::Future<Output = reqwest::Result<reqwest::Response>>,
+{
+    let mut attempt = 0;
+
+    loop {
+        match request().await {
+            Ok(response) if response.status().is_success() => return Ok(response),
+            Ok(response) => {
+                let status = response.status();
+                if !is_retryable(status) {
+                    return Err(worker::Error::RustError(format!("Non-retryable HTTP status: {}", status)));
+                }
+                if attempt >= retry_config.max_retries {
+                    return Err(worker::Error::RustError(format!(
+                        "HTTP error after {} attempts: {}",
+                        retry_config.max_retries, status
+                    )));
+                }
+                let delay = retry_after(&response).unwrap_or_else(|| backoff_delay(retry_config, attempt));
+                attempt += 1;
+                crate::metrics::record_retry();
+                console_log!("Retry attempt {} for request (status {})", attempt, status);
+                worker::Delay::from(delay).await;
+            }
+            Err(e) => {
+                if attempt >= retry_config.max_retries {
+                    return Err(worker::Error::RustError(format!(
+                        "Request failed after {} attempts: {}",
+                        retry_config.max_retries, e
+                    )));
+                }
+                let delay = backoff_delay(retry_config, attempt);
+                attempt += 1;
+                crate::metrics::record_retry();
+                console_log!("Retry attempt {} for request", attempt);
+                worker::Delay::from(delay).await;
+            }
+        }
+    }
+}
+
+/// Only idempotent/transient statuses are worth retrying; 4xx like 400/401/403
+/// indicate a request the upstream will never accept.
+fn is_retryable(status: StatusCode) -> bool {
+    matches!(status.as_u16(), 429 | 500 | 502 | 503 | 504)
+}
+
+/// Upper bound for the full-jitter backoff window: `base * 2^attempt`,
+/// capped at `max_delay`. Split out from [`backoff_delay`] so the capping
+/// logic is testable without depending on `js_sys::Math::random`.
+fn backoff_upper_bound(retry_config: &RetryConfig, attempt: u32) -> Duration {
+    retry_config
+        .base_delay
+        .saturating_mul(1u32 << attempt.min(20))
+        .min(retry_config.max_delay)
+}
+
+/// Exponential backoff with full jitter: `random(0, base * 2^attempt)`,
+/// capped at `max_delay`.
+fn backoff_delay(retry_config: &RetryConfig, attempt: u32) -> Duration {
+    let upper_bound = backoff_upper_bound(retry_config, attempt);
+    Duration::from_millis((js_sys::Math::random() * upper_bound.as_millis() as f64) as u64)
+}
+
+/// Parse `Retry-After`, supporting both the seconds and HTTP-date forms.
+fn retry_after(response: &reqwest::Response) -> Option<Duration> {
+    let value = response.headers().get(reqwest::header::RETRY_AFTER)?.to_str().ok()?;
+    parse_retry_after(value)
+}
+
+/// Parse a raw `Retry-After` header value in either the seconds or
+/// HTTP-date form. Split out from [`retry_after`] so it's testable without
+/// constructing a `reqwest::Response`.
+fn parse_retry_after(value: &str) -> Option<Duration> {
+    if let Ok(seconds) = value.parse::<u64>() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let target = chrono::DateTime::parse_from_rfc2822(value).ok()?.with_timezone(&Utc);
+    Some((target - Utc::now()).to_std().unwrap_or(Duration::ZERO))
+}
+
+/// Build the reqwest client shared by the fetch handler and the queue consumer.
+pub fn build_client() -> worker::Result<Client> {
+    Client::builder()
+        .timeout(Duration::from_secs(10))
+        .user_agent("Cloudflare-Worker-Scraper/1.0")
+        .build()
+        .map_err(|e| worker::Error::RustError(format!("Failed to create client: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config() -> RetryConfig {
+        RetryConfig {
+            max_retries: 3,
+            base_delay: Duration::from_millis(1_000),
+            max_delay: Duration::from_millis(8_000),
+        }
+    }
+
+    #[test]
+    fn is_retryable_covers_only_idempotent_transient_statuses() {
+        for status in [429, 500, 502, 503, 504] {
+            assert!(is_retryable(StatusCode::from_u16(status).unwrap()));
+        }
+        for status in [400, 401, 403, 404, 200] {
+            assert!(!is_retryable(StatusCode::from_u16(status).unwrap()));
+        }
+    }
+
+    #[test]
+    fn backoff_upper_bound_doubles_each_attempt() {
+        let config = config();
+        assert_eq!(backoff_upper_bound(&config, 0), Duration::from_millis(1_000));
+        assert_eq!(backoff_upper_bound(&config, 1), Duration::from_millis(2_000));
+        assert_eq!(backoff_upper_bound(&config, 2), Duration::from_millis(4_000));
+    }
+
+    #[test]
+    fn backoff_upper_bound_caps_at_max_delay() {
+        let config = config();
+        assert_eq!(backoff_upper_bound(&config, 10), config.max_delay);
+    }
+
+    #[test]
+    fn parse_retry_after_accepts_seconds_form() {
+        assert_eq!(parse_retry_after("120"), Some(Duration::from_secs(120)));
+    }
+
+    #[test]
+    fn parse_retry_after_accepts_http_date_form() {
+        let future = Utc::now() + chrono::Duration::seconds(30);
+        let header = future.to_rfc2822();
+        let delay = parse_retry_after(&header).expect("should parse HTTP-date form");
+        // Allow a little slack for the time elapsed between formatting and parsing.
+        assert!(delay <= Duration::from_secs(31));
+    }
+
+    #[test]
+    fn parse_retry_after_rejects_garbage() {
+        assert_eq!(parse_retry_after("not-a-valid-value"), None);
+    }
+}
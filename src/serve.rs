@@ -0,0 +1,204 @@
+use chrono::{DateTime, Utc};
+use worker::{Env, Headers, Request, Response, Result};
+
+use crate::hash::{content_key, meta_key};
+use crate::manifest;
+use crate::store::{build_store, Store};
+
+/// Default `Cache-Control` max-age (seconds) for served Markdown, overridable
+/// via the `MARKDOWN_CACHE_MAX_AGE` env var.
+const DEFAULT_MAX_AGE_SECS: u64 = 3600;
+
+/// Handle `GET /markdown/<key>`, serving stored Markdown looked up via
+/// [`content_key`] with ETag/Last-Modified/Range support.
+pub async fn get_markdown(req: &Request, env: &Env, key: &str) -> Result<Response> {
+    let store = build_store(env)?;
+    let hash = key.strip_suffix(".md").unwrap_or(key);
+
+    let bytes = match store.get(&content_key(hash)).await? {
+        Some(bytes) => bytes,
+        None => return Response::error("Not Found", 404),
+    };
+
+    let etag = format!("\"{}\"", hash);
+    let fetched_at = read_fetched_at(store.as_ref(), hash).await?;
+    let last_modified = fetched_at.map(format_http_date);
+
+    if let Some(if_none_match) = req.headers().get("If-None-Match")? {
+        if if_none_match == etag {
+            return not_modified(&etag, last_modified.as_deref());
+        }
+    } else if let (Some(since), Some(fetched_at)) = (req.headers().get("If-Modified-Since")?, fetched_at) {
+        if let Some(since_ms) = parse_http_date(&since) {
+            if fetched_at <= since_ms {
+                return not_modified(&etag, last_modified.as_deref());
+            }
+        }
+    }
+
+    let max_age = env
+        .var("MARKDOWN_CACHE_MAX_AGE")
+        .ok()
+        .and_then(|v| v.to_string().parse::<u64>().ok())
+        .unwrap_or(DEFAULT_MAX_AGE_SECS);
+
+    let total_len = bytes.len();
+    let range = req.headers().get("Range")?.map(|header| parse_range(total_len, &header));
+
+    let (status, body, content_range) = match range {
+        Some(RangeOutcome::Satisfiable(start, end)) => (
+            206,
+            bytes[start..=end].to_vec(),
+            Some(format!("bytes {}-{}/{}", start, end, total_len)),
+        ),
+        Some(RangeOutcome::Unsatisfiable) => {
+            let mut headers = Headers::new();
+            headers.set("Content-Range", &format!("bytes */{}", total_len))?;
+            return Ok(Response::empty()?.with_status(416).with_headers(headers));
+        }
+        Some(RangeOutcome::None) | None => (200, bytes, None),
+    };
+
+    let mut headers = Headers::new();
+    headers.set("Content-Type", "text/markdown; charset=utf-8")?;
+    headers.set("ETag", &etag)?;
+    if let Some(last_modified) = &last_modified {
+        headers.set("Last-Modified", last_modified)?;
+    }
+    headers.set("Accept-Ranges", "bytes")?;
+    headers.set("Cache-Control", &format!("public, max-age={}", max_age))?;
+    if let Some(content_range) = content_range {
+        headers.set("Content-Range", &content_range)?;
+    }
+
+    Ok(Response::from_bytes(body)?
+        .with_status(status)
+        .with_headers(headers))
+}
+
+fn not_modified(etag: &str, last_modified: Option<&str>) -> Result<Response> {
+    let mut headers = Headers::new();
+    headers.set("ETag", etag)?;
+    if let Some(last_modified) = last_modified {
+        headers.set("Last-Modified", last_modified)?;
+    }
+    Ok(Response::empty()?.with_status(304).with_headers(headers))
+}
+
+/// Read the first-stored timestamp sidecar written alongside `hash`'s
+/// content, if any.
+async fn read_fetched_at(store: &dyn Store, hash: &str) -> Result<Option<i64>> {
+    match store.get(&meta_key(hash)).await? {
+        Some(bytes) => Ok(serde_json::from_slice(&bytes).ok()),
+        None => Ok(None),
+    }
+}
+
+/// Handle `GET /manifest?url=<url>`: resolves a source URL to the content
+/// hash/timestamp of its most recently stored Markdown.
+pub async fn get_manifest(req: &Request, env: &Env) -> Result<Response> {
+    let target_url = match req.url()?.query_pairs().find(|(k, _)| k == "url") {
+        Some((_, v)) => v.into_owned(),
+        None => return Response::error("Missing 'url' query parameter", 400),
+    };
+
+    let store = build_store(env)?;
+    match manifest::lookup(store.as_ref(), &target_url).await? {
+        Some(entry) => Response::from_json(&entry),
+        None => Response::error("Not Found", 404),
+    }
+}
+
+/// Outcome of parsing a `Range` header against a known `total_len`.
+#[derive(Debug, PartialEq, Eq)]
+enum RangeOutcome {
+    /// No `Range` header, or one we can't parse — serve the full body.
+    None,
+    /// A satisfiable inclusive byte range.
+    Satisfiable(usize, usize),
+    /// Syntactically valid but outside `total_len` — 416, not 200.
+    Unsatisfiable,
+}
+
+/// Parse a `Range: bytes=<start>-<end>` header into an inclusive byte range
+/// clipped to `total_len`. Only the single-range form is supported.
+fn parse_range(total_len: usize, header: &str) -> RangeOutcome {
+    let Some(spec) = header.strip_prefix("bytes=") else {
+        return RangeOutcome::None;
+    };
+    let Some((start_s, end_s)) = spec.split_once('-') else {
+        return RangeOutcome::None;
+    };
+    let Ok(start) = start_s.parse::<usize>() else {
+        return RangeOutcome::None;
+    };
+    let end = if end_s.is_empty() {
+        total_len.saturating_sub(1)
+    } else {
+        match end_s.parse::<usize>() {
+            Ok(end) => end.min(total_len.saturating_sub(1)),
+            Err(_) => return RangeOutcome::None,
+        }
+    };
+
+    if total_len == 0 || start > end || start >= total_len {
+        return RangeOutcome::Unsatisfiable;
+    }
+    RangeOutcome::Satisfiable(start, end)
+}
+
+fn format_http_date(fetched_at_ms: i64) -> String {
+    DateTime::<Utc>::from_timestamp_millis(fetched_at_ms)
+        .unwrap_or_default()
+        .format("%a, %d %b %Y %H:%M:%S GMT")
+        .to_string()
+}
+
+fn parse_http_date(value: &str) -> Option<i64> {
+    DateTime::parse_from_rfc2822(value)
+        .ok()
+        .map(|dt| dt.timestamp_millis())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_markdown_looks_up_the_same_key_the_consumer_writes() {
+        // The route receives the `<hash>.md` filename from the path; it must
+        // resolve to the same store key the queue consumer writes content
+        // under, or every legitimate request 404s.
+        let hash = "deadbeef";
+        let key_from_route = format!("{}.md", hash);
+        let derived_hash = key_from_route.strip_suffix(".md").unwrap_or(&key_from_route);
+        assert_eq!(content_key(derived_hash), content_key(hash));
+    }
+
+    #[test]
+    fn parse_range_clips_open_ended_range_to_total_len() {
+        assert_eq!(parse_range(100, "bytes=50-"), RangeOutcome::Satisfiable(50, 99));
+    }
+
+    #[test]
+    fn parse_range_clips_end_past_total_len() {
+        assert_eq!(parse_range(10, "bytes=0-1000"), RangeOutcome::Satisfiable(0, 9));
+    }
+
+    #[test]
+    fn parse_range_rejects_start_past_total_len_as_unsatisfiable() {
+        assert_eq!(parse_range(10, "bytes=20-30"), RangeOutcome::Unsatisfiable);
+    }
+
+    #[test]
+    fn parse_range_treats_malformed_header_as_absent() {
+        assert_eq!(parse_range(10, "not-a-range"), RangeOutcome::None);
+    }
+
+    #[test]
+    fn parse_http_date_round_trips_format_http_date() {
+        let ms = 1_700_000_000_000;
+        let formatted = format_http_date(ms);
+        assert_eq!(parse_http_date(&formatted), Some(ms));
+    }
+}
@@ -0,0 +1,161 @@
+use async_trait::async_trait;
+use std::time::Duration;
+use worker::{kv::KvStore, Bucket, Env, Result};
+
+/// Persistence backend for stored Markdown and the manifest object.
+///
+/// Implemented for R2, Workers KV, and an external S3-compatible endpoint so
+/// the scraper isn't hard-wired to a single Cloudflare binding; see
+/// [`build_store`] for backend selection.
+#[async_trait(?Send)]
+pub trait Store {
+    async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<()>;
+    async fn head(&self, key: &str) -> Result<bool>;
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>>;
+}
+
+/// Select and construct the configured [`Store`] backend from the
+/// `STORAGE_BACKEND` env var (`r2` (default), `kv`, or `s3`).
+pub fn build_store(env: &Env) -> Result<Box<dyn Store>> {
+    let backend = env
+        .var("STORAGE_BACKEND")
+        .map(|v| v.to_string())
+        .unwrap_or_else(|_| "r2".to_string());
+
+    match backend.as_str() {
+        "kv" => Ok(Box::new(KvStoreBackend(env.kv("SCRAPER_KV")?))),
+        "s3" => Ok(Box::new(S3Store::from_env(env)?)),
+        _ => Ok(Box::new(R2Store(env.bucket("SCRAPER_BUCKET")?))),
+    }
+}
+
+/// R2-backed store, the default binding used before backends were
+/// pluggable.
+pub struct R2Store(pub Bucket);
+
+#[async_trait(?Send)]
+impl Store for R2Store {
+    async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<()> {
+        self.0.put(key, bytes).execute().await?;
+        Ok(())
+    }
+
+    async fn head(&self, key: &str) -> Result<bool> {
+        Ok(self.0.head(key).await?.is_some())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        match self.0.get(key).execute().await? {
+            Some(object) => match object.body() {
+                Some(body) => Ok(Some(body.bytes().await?)),
+                None => Ok(None),
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+/// Workers KV-backed store.
+pub struct KvStoreBackend(pub KvStore);
+
+#[async_trait(?Send)]
+impl Store for KvStoreBackend {
+    async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<()> {
+        self.0.put_bytes(key, &bytes)?.execute().await
+    }
+
+    async fn head(&self, key: &str) -> Result<bool> {
+        Ok(self.0.get(key).bytes().await?.is_some())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        self.0.get(key).bytes().await
+    }
+}
+
+/// Store backed by a signed-request client against an external
+/// S3-compatible endpoint, in the spirit of Garage/rusty-s3.
+pub struct S3Store {
+    bucket: rusty_s3::Bucket,
+    credentials: rusty_s3::Credentials,
+    client: reqwest::Client,
+}
+
+impl S3Store {
+    fn from_env(env: &Env) -> Result<Self> {
+        let endpoint = env.secret("S3_ENDPOINT")?.to_string();
+        let region = env
+            .var("S3_REGION")
+            .map(|v| v.to_string())
+            .unwrap_or_else(|_| "auto".to_string());
+        let bucket_name = env.secret("S3_BUCKET")?.to_string();
+        let key_id = env.secret("S3_ACCESS_KEY_ID")?.to_string();
+        let secret = env.secret("S3_SECRET_ACCESS_KEY")?.to_string();
+
+        let endpoint = endpoint
+            .parse()
+            .map_err(|e| worker::Error::RustError(format!("Invalid S3 endpoint: {}", e)))?;
+        let bucket = rusty_s3::Bucket::new(endpoint, rusty_s3::UrlStyle::Path, bucket_name, region)
+            .map_err(|e| worker::Error::RustError(format!("Invalid S3 bucket config: {}", e)))?;
+        let credentials = rusty_s3::Credentials::new(key_id, secret);
+
+        Ok(Self {
+            bucket,
+            credentials,
+            client: reqwest::Client::new(),
+        })
+    }
+}
+
+const SIGNED_URL_TTL: Duration = Duration::from_secs(60);
+
+#[async_trait(?Send)]
+impl Store for S3Store {
+    async fn put(&self, key: &str, bytes: Vec<u8>) -> Result<()> {
+        let action = self.bucket.put_object(Some(&self.credentials), key);
+        let url = action.sign(SIGNED_URL_TTL);
+        self.client
+            .put(url)
+            .body(bytes)
+            .send()
+            .await
+            .and_then(reqwest::Response::error_for_status)
+            .map_err(|e| worker::Error::RustError(format!("S3 put failed: {}", e)))?;
+        Ok(())
+    }
+
+    async fn head(&self, key: &str) -> Result<bool> {
+        let action = self.bucket.head_object(Some(&self.credentials), key);
+        let url = action.sign(SIGNED_URL_TTL);
+        let response = self
+            .client
+            .head(url)
+            .send()
+            .await
+            .map_err(|e| worker::Error::RustError(format!("S3 head failed: {}", e)))?;
+        Ok(response.status().is_success())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Vec<u8>>> {
+        let action = self.bucket.get_object(Some(&self.credentials), key);
+        let url = action.sign(SIGNED_URL_TTL);
+        let response = self
+            .client
+            .get(url)
+            .send()
+            .await
+            .map_err(|e| worker::Error::RustError(format!("S3 get failed: {}", e)))?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        let bytes = response
+            .error_for_status()
+            .map_err(|e| worker::Error::RustError(format!("S3 get failed: {}", e)))?
+            .bytes()
+            .await
+            .map_err(|e| worker::Error::RustError(format!("S3 get failed: {}", e)))?;
+        Ok(Some(bytes.to_vec()))
+    }
+}